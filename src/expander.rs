@@ -0,0 +1,351 @@
+//! Transport backends for the various I2C I/O-expander chips used on "backpack" boards.
+//!
+//! [`I2cLcdBackpack`][crate::I2cLcdBackpack] talks to the LCD through an [`Expander`], which knows how to turn a
+//! raw port value into whatever I2C transaction a particular expander chip expects. The default backend is
+//! [`Pcf8574`], matching the NXP PCF8574/PCF8574A chips this crate originally targeted. Expanders with a
+//! wider port, like the 16-bit [`Pcf8575`], use a 16-bit [`Expander::State`] instead of the usual `u8`.
+
+use core::{
+    fmt::Debug,
+    ops::{BitAnd, BitAndAssign, BitOrAssign, Not},
+};
+use embedded_hal::i2c::{I2c, SevenBitAddress};
+
+/// A raw port-state value for an [`Expander`] backend: `u8` for 8-bit expanders like the PCF8574, `u16`
+/// for 16-bit expanders like the PCF8575.
+pub trait PortState:
+    Copy
+    + Debug
+    + Default
+    + PartialEq
+    + BitOrAssign
+    + BitAndAssign
+    + BitAnd<Output = Self>
+    + Not<Output = Self>
+{
+    /// The value with only `pin`'s bit set.
+    fn bit(pin: u8) -> Self;
+}
+
+impl PortState for u8 {
+    fn bit(pin: u8) -> Self {
+        1 << pin
+    }
+}
+
+impl PortState for u16 {
+    fn bit(pin: u8) -> Self {
+        1 << pin
+    }
+}
+
+/// A backend that knows how to read and write the output pins of an I2C I/O-expander chip.
+///
+/// Implementations own only the chip-specific protocol (register addresses, direction bits, port width,
+/// and so on); [`I2cLcdBackpack`][crate::I2cLcdBackpack] is responsible for mapping LCD signals onto pin
+/// numbers.
+pub trait Expander<T: I2c<SevenBitAddress>> {
+    /// The raw port-state type for this expander, and hence the width of a single `write_port`/`read_port`
+    /// transaction: `u8` for an 8-bit expander, `u16` for a 16-bit one.
+    type State: PortState;
+
+    /// The number of pins addressable on this expander's port (`8` or `16`).
+    const PIN_COUNT: u8;
+
+    /// Perform any one-time setup the chip needs before its port can be written or read, such as
+    /// configuring direction registers. Called once when the [`I2cLcdBackpack`][crate::I2cLcdBackpack] is
+    /// constructed. The default implementation does nothing, which is correct for expanders (like the
+    /// PCF8574) that have no direction registers.
+    fn init(&mut self, i2c: &mut T, address: u8) -> Result<(), T::Error> {
+        let _ = (i2c, address);
+        Ok(())
+    }
+
+    /// Write `value` to the expander's output port.
+    fn write_port(&mut self, i2c: &mut T, address: u8, value: Self::State) -> Result<(), T::Error>;
+
+    /// Read the current state of the expander's input port.
+    ///
+    /// `readable` has a `1` bit for each pin the caller is actually about to sample (on a PCF8574-style
+    /// backpack this is only ever the D4-D7 nibble). Backends with a direction register must flip only
+    /// these bits to input for the duration of the read and restore the rest, so pins outside `readable`
+    /// (EN, RS, RW, the backlight) keep driving their last-written level the whole time; flipping the
+    /// entire port would release EN while the `lcd` crate is relying on it staying asserted.
+    fn read_port(
+        &mut self,
+        i2c: &mut T,
+        address: u8,
+        readable: Self::State,
+    ) -> Result<Self::State, T::Error>;
+}
+
+/// Expander backend for the NXP PCF8574/PCF8574A quasi-bidirectional I/O expander.
+///
+/// This is a direct, single-byte read/write with no registers: the chip has no direction bits, so a pin
+/// reads back whatever was last written to it unless it's being pulled low externally.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pcf8574;
+
+impl<T: I2c<SevenBitAddress>> Expander<T> for Pcf8574 {
+    type State = u8;
+    const PIN_COUNT: u8 = 8;
+
+    fn write_port(&mut self, i2c: &mut T, address: u8, value: u8) -> Result<(), T::Error> {
+        i2c.write(address, &[value])
+    }
+
+    fn read_port(&mut self, i2c: &mut T, address: u8, _readable: u8) -> Result<u8, T::Error> {
+        let mut result = [0u8; 1];
+        i2c.read(address, &mut result)?;
+        Ok(result[0])
+    }
+}
+
+/// Expander backend for the NXP PCF8575, a 16-bit sibling of the PCF8574 addressing pins `P0`..`P17`.
+///
+/// Like the PCF8574 this has no registers and no direction bits, but each transaction transfers two bytes,
+/// least-significant byte first, covering both halves of the port at once.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pcf8575;
+
+impl<T: I2c<SevenBitAddress>> Expander<T> for Pcf8575 {
+    type State = u16;
+    const PIN_COUNT: u8 = 16;
+
+    fn write_port(&mut self, i2c: &mut T, address: u8, value: u16) -> Result<(), T::Error> {
+        i2c.write(address, &value.to_le_bytes())
+    }
+
+    fn read_port(&mut self, i2c: &mut T, address: u8, _readable: u16) -> Result<u16, T::Error> {
+        let mut result = [0u8; 2];
+        i2c.read(address, &mut result)?;
+        Ok(u16::from_le_bytes(result))
+    }
+}
+
+/// NXP PCA9554 output-port register (used for writes).
+const PCA9554_REG_OUTPUT: u8 = 0x01;
+
+/// NXP PCA9554 input-port register (used for reads).
+const PCA9554_REG_INPUT: u8 = 0x00;
+
+/// NXP PCA9554 configuration (data-direction) register: one bit per pin, `1` is input, `0` is output.
+const PCA9554_REG_CONFIG: u8 = 0x03;
+
+/// Expander backend for the NXP PCA9554 register-addressed I/O expander.
+///
+/// This is PCA9554-only: the PCA9555 is a *different* chip with its own 16-bit register map (separate P0
+/// and P1 input/output/config registers at other offsets), not a drop-in register-compatible sibling, so
+/// driving one through this backend would write to the wrong registers. A PCA9555 backend would need its
+/// own `Expander` impl with a `u16` [`PortState`].
+///
+/// Unlike the PCF8574, this chip is a real register file: writes go to the output-port register and reads
+/// come from the input-port register. Since the backpack wiring never switches the LCD's `RW` line high
+/// except to read the busy flag, only the pins the caller is about to sample are flipped to inputs in the
+/// configuration register for the duration of a read and restored to outputs afterward, so EN (and any
+/// other pin outside the read) keeps driving its last-written level throughout.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Pca9554;
+
+impl<T: I2c<SevenBitAddress>> Expander<T> for Pca9554 {
+    type State = u8;
+    const PIN_COUNT: u8 = 8;
+
+    fn write_port(&mut self, i2c: &mut T, address: u8, value: u8) -> Result<(), T::Error> {
+        i2c.write(address, &[PCA9554_REG_OUTPUT, value])
+    }
+
+    fn read_port(&mut self, i2c: &mut T, address: u8, readable: u8) -> Result<u8, T::Error> {
+        i2c.write(address, &[PCA9554_REG_CONFIG, readable])?;
+        let mut result = [0u8; 1];
+        i2c.write_read(address, &[PCA9554_REG_INPUT], &mut result)?;
+        i2c.write(address, &[PCA9554_REG_CONFIG, 0x00])?;
+        Ok(result[0])
+    }
+}
+
+/// Microchip MCP23008 I/O-direction register: one bit per pin, `1` is input, `0` is output.
+const MCP23008_REG_IODIR: u8 = 0x00;
+
+/// Microchip MCP23008 GPIO port register (used for both reads and writes).
+const MCP23008_REG_GPIO: u8 = 0x09;
+
+/// Expander backend for the Microchip MCP23008 I/O expander used on the
+/// [Adafruit I2C/SPI LCD backpack](https://learn.adafruit.com/i2c-spi-lcd-backpack).
+///
+/// All 8 pins are configured as outputs once, at construction time; a read flips only the pins the caller
+/// is about to sample to inputs just long enough to sample the GPIO register, then restores them to
+/// outputs, the same way [`Pca9554`] handles its configuration register.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Mcp23008;
+
+impl<T: I2c<SevenBitAddress>> Expander<T> for Mcp23008 {
+    type State = u8;
+    const PIN_COUNT: u8 = 8;
+
+    fn init(&mut self, i2c: &mut T, address: u8) -> Result<(), T::Error> {
+        i2c.write(address, &[MCP23008_REG_IODIR, 0x00])
+    }
+
+    fn write_port(&mut self, i2c: &mut T, address: u8, value: u8) -> Result<(), T::Error> {
+        i2c.write(address, &[MCP23008_REG_GPIO, value])
+    }
+
+    fn read_port(&mut self, i2c: &mut T, address: u8, readable: u8) -> Result<u8, T::Error> {
+        i2c.write(address, &[MCP23008_REG_IODIR, readable])?;
+        let mut result = [0u8; 1];
+        i2c.write_read(address, &[MCP23008_REG_GPIO], &mut result)?;
+        i2c.write(address, &[MCP23008_REG_IODIR, 0x00])?;
+        Ok(result[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use embedded_hal::i2c::{Error, ErrorKind, ErrorType, Operation};
+
+    const ADDR: u8 = 0x20;
+
+    #[derive(Debug)]
+    struct MockError;
+
+    impl Error for MockError {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    /// Records every `write`/`write_read` transaction so a test can assert the exact register sequence an
+    /// `Expander` backend sends, and lets a test preload the bytes a `read`/`write_read` should return.
+    #[derive(Debug, Default)]
+    struct Mock {
+        writes: [[u8; 2]; 4],
+        write_lens: [usize; 4],
+        write_count: usize,
+        reply: [u8; 2],
+    }
+
+    impl Mock {
+        fn nth_write(&self, n: usize) -> &[u8] {
+            &self.writes[n][..self.write_lens[n]]
+        }
+    }
+
+    impl ErrorType for Mock {
+        type Error = MockError;
+    }
+
+    impl I2c<SevenBitAddress> for Mock {
+        fn read(&mut self, _address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            buffer.copy_from_slice(&self.reply[..buffer.len()]);
+            Ok(())
+        }
+
+        fn write(&mut self, _address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+            let i = self.write_count;
+            self.writes[i][..bytes.len()].copy_from_slice(bytes);
+            self.write_lens[i] = bytes.len();
+            self.write_count += 1;
+            Ok(())
+        }
+
+        fn write_read(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            self.write(address, bytes)?;
+            self.read(address, buffer)
+        }
+
+        fn transaction(
+            &mut self,
+            _address: u8,
+            _operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            unimplemented!("no Expander backend uses I2c::transaction")
+        }
+    }
+
+    #[test]
+    fn pca9554_write_port_sends_the_output_register_and_value() {
+        let mut bus = Mock::default();
+        Pca9554.write_port(&mut bus, ADDR, 0b0010_1010).unwrap();
+
+        assert_eq!(bus.write_count, 1);
+        assert_eq!(bus.nth_write(0), [PCA9554_REG_OUTPUT, 0b0010_1010]);
+    }
+
+    #[test]
+    fn pca9554_read_port_scopes_the_config_register_to_the_readable_mask() {
+        let mut bus = Mock {
+            reply: [0x55, 0],
+            ..Mock::default()
+        };
+
+        let value = Pca9554.read_port(&mut bus, ADDR, 0b0000_1111).unwrap();
+
+        assert_eq!(value, 0x55);
+        assert_eq!(bus.write_count, 3);
+        assert_eq!(bus.nth_write(0), [PCA9554_REG_CONFIG, 0b0000_1111]);
+        assert_eq!(bus.nth_write(1), [PCA9554_REG_INPUT]);
+        assert_eq!(bus.nth_write(2), [PCA9554_REG_CONFIG, 0x00]);
+    }
+
+    #[test]
+    fn mcp23008_init_configures_all_pins_as_outputs() {
+        let mut bus = Mock::default();
+        Mcp23008.init(&mut bus, ADDR).unwrap();
+
+        assert_eq!(bus.write_count, 1);
+        assert_eq!(bus.nth_write(0), [MCP23008_REG_IODIR, 0x00]);
+    }
+
+    #[test]
+    fn mcp23008_write_port_sends_the_gpio_register_and_value() {
+        let mut bus = Mock::default();
+        Mcp23008.write_port(&mut bus, ADDR, 0b0101_0101).unwrap();
+
+        assert_eq!(bus.write_count, 1);
+        assert_eq!(bus.nth_write(0), [MCP23008_REG_GPIO, 0b0101_0101]);
+    }
+
+    #[test]
+    fn mcp23008_read_port_scopes_iodir_to_the_readable_mask() {
+        let mut bus = Mock {
+            reply: [0xaa, 0],
+            ..Mock::default()
+        };
+
+        let value = Mcp23008.read_port(&mut bus, ADDR, 0b0000_1111).unwrap();
+
+        assert_eq!(value, 0xaa);
+        assert_eq!(bus.write_count, 3);
+        assert_eq!(bus.nth_write(0), [MCP23008_REG_IODIR, 0b0000_1111]);
+        assert_eq!(bus.nth_write(1), [MCP23008_REG_GPIO]);
+        assert_eq!(bus.nth_write(2), [MCP23008_REG_IODIR, 0x00]);
+    }
+
+    #[test]
+    fn pcf8575_write_port_sends_both_bytes_least_significant_first() {
+        let mut bus = Mock::default();
+        Pcf8575.write_port(&mut bus, ADDR, 0x1234).unwrap();
+
+        assert_eq!(bus.write_count, 1);
+        assert_eq!(bus.nth_write(0), [0x34, 0x12]);
+    }
+
+    #[test]
+    fn pcf8575_read_port_decodes_both_bytes_least_significant_first() {
+        let mut bus = Mock {
+            reply: [0x34, 0x12],
+            ..Mock::default()
+        };
+
+        let value = Pcf8575.read_port(&mut bus, ADDR, 0xffff).unwrap();
+
+        assert_eq!(value, 0x1234);
+    }
+}
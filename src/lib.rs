@@ -15,16 +15,45 @@
 //! The default pin configuration was determined from various schematics found on the Internet and verified by
 //! probing pin connections on the board I have (whose actual manufacturer is unknown).
 //!
-//! This is _not_ (yet) compatible with the [Adafruit I2C/SPI Backpack](https://learn.adafruit.com/i2c-spi-lcd-backpack);
-//! the IC used requires a more complex communication protocol over I2C. (The pin assignment is also different.)
+//! The [Adafruit I2C/SPI Backpack](https://learn.adafruit.com/i2c-spi-lcd-backpack) is also supported: it's
+//! built around an MCP23008 rather than a PCF8574, so use the [`Mcp23008`] expander backend together with
+//! [`I2cLcdPinConfig::adafruit_mcp23008`] and [`I2cLcdBackpack::new_with_expander`].
+//!
+//! Boards built around other I/O expanders are supported the same way: see [`Expander`] for the full list
+//! of backends, including the register-addressed PCA9554 ([`Pca9554`]) and the 16-bit PCF8575
+//! ([`Pcf8575`]).
+//!
+//! ## Sharing the I2C bus with other devices
+//! [`I2cLcdBackpack`] takes its I2C driver by value rather than by reference, but it only requires that
+//! the driver implement [`embedded_hal::i2c::I2c`] — it doesn't need to be the bus peripheral itself. Pass
+//! it a shared-bus proxy such as [`embedded-hal-bus`](https://docs.rs/embedded-hal-bus)'s
+//! `RefCellDevice`/`AtomicDevice` (or any other `I2c`-implementing wrapper) and the backpack will happily
+//! sit on a bus alongside other sensors:
+//!
+//! ```ignore
+//! use core::cell::RefCell;
+//! use embedded_hal_bus::i2c::RefCellDevice;
+//!
+//! let i2c_bus = RefCell::new(i2c);
+//! let lcd = I2cLcdBackpack::new(RefCellDevice::new(&i2c_bus), LCD_ADDR);
+//! let rtc = Ds1307::new(RefCellDevice::new(&i2c_bus));
+//! ```
 #![no_std]
 #![warn(clippy::all)]
 #![deny(rustdoc::missing_crate_level_docs)]
 #![deny(rustdoc::broken_intra_doc_links)]
 #![deny(missing_docs)]
 
+mod expander;
+
+pub use expander::{Expander, Mcp23008, Pca9554, Pcf8574, Pcf8575, PortState};
+
 use {
-    core::fmt::{Debug, Formatter, Result as FmtResult},
+    core::{
+        fmt::{Debug, Formatter, Result as FmtResult},
+        iter::Chain,
+        ops::RangeInclusive,
+    },
     embedded_hal::i2c::{I2c, SevenBitAddress},
     lcd::{Backlight, FunctionMode, Hardware},
 };
@@ -56,11 +85,13 @@ use {
 /// display.clear();
 /// display.print("Hello world!");
 /// ```
-pub struct I2cLcdBackpack<T> {
+pub struct I2cLcdBackpack<T: I2c<SevenBitAddress>, E: Expander<T> = Pcf8574> {
     driver: T,
+    expander: E,
     address: u8,
-    state: u8,
+    state: E::State,
     pins: I2cLcdPinConfig,
+    last_error: Option<T::Error>,
 }
 
 const DEFAULT_RS_PIN: u8 = 0;
@@ -74,7 +105,15 @@ const DEFAULT_BACKLIGHT_PIN: u8 = 3;
 
 const RW_PIN_NONE: u8 = 0xff;
 
-impl<T> I2cLcdBackpack<T> {
+const ADAFRUIT_MCP23008_RS_PIN: u8 = 1;
+const ADAFRUIT_MCP23008_EN_PIN: u8 = 2;
+const ADAFRUIT_MCP23008_D4_PIN: u8 = 3;
+const ADAFRUIT_MCP23008_D5_PIN: u8 = 4;
+const ADAFRUIT_MCP23008_D6_PIN: u8 = 5;
+const ADAFRUIT_MCP23008_D7_PIN: u8 = 6;
+const ADAFRUIT_MCP23008_BACKLIGHT_PIN: u8 = 7;
+
+impl<T: I2c<SevenBitAddress>> I2cLcdBackpack<T, Pcf8574> {
     /// Create a new LCD I2C backpack hardware struct using the given I2C HAL driver communicating with
     /// a PCF8574 chip at the given address, using the default (common) pin assignment.
     pub fn new(driver: T, address: u8) -> Self {
@@ -84,62 +123,105 @@ impl<T> I2cLcdBackpack<T> {
     /// Create a new LCD I2C driver communicating using the given I2C HAL driver communicating with
     /// a PCF8574 chip at the given address, using a custom pin assignment.
     pub fn new_with_pins(driver: T, address: u8, pins: I2cLcdPinConfig) -> Self {
+        Self::new_with_expander(driver, address, pins, Pcf8574)
+    }
+}
+
+impl<T: I2c<SevenBitAddress>, E: Expander<T>> I2cLcdBackpack<T, E> {
+    /// Create a new LCD I2C backpack hardware struct using the given I2C HAL driver and I/O-expander
+    /// backend, using a custom pin assignment.
+    ///
+    /// Use this instead of [`new`][Self::new]/[`new_with_pins`][Self::new_with_pins] when the backpack is
+    /// built around a register-addressed expander such as the PCA9554 (see [`Pca9554`]) or the MCP23008
+    /// used on the Adafruit backpack (see [`Mcp23008`]) rather than a PCF8574.
+    pub fn new_with_expander(
+        mut driver: T,
+        address: u8,
+        pins: I2cLcdPinConfig,
+        mut expander: E,
+    ) -> Self {
+        pins.check_pin_count(E::PIN_COUNT);
+        expander.init(&mut driver, address).unwrap();
         Self {
             driver,
+            expander,
             address,
-            state: 0,
+            state: E::State::default(),
             pins,
+            last_error: None,
         }
     }
+
+    /// Take the most recently recorded I2C transport error, if any, clearing it.
+    ///
+    /// [`Hardware`] and [`Backlight`] methods can't return a [`Result`], so a NAK or other transport
+    /// failure from [`apply`][Hardware::apply], [`read_data`][Hardware::read_data], or
+    /// [`set_backlight`][Backlight::set_backlight] is latched here instead of panicking. Poll this
+    /// after driving the display to notice wiring or bus faults without aborting.
+    pub fn take_error(&mut self) -> Option<T::Error> {
+        self.last_error.take()
+    }
+
+    /// Returns `true` if a transport error has been recorded since the last call to
+    /// [`take_error`][Self::take_error].
+    pub fn has_error(&self) -> bool {
+        self.last_error.is_some()
+    }
 }
 
+/// The widest pin index any currently supported [`Expander`] backend can address. [`I2cLcdPinConfig`]'s
+/// builder methods only reject pins above this, since the config isn't paired with a backend until
+/// [`I2cLcdBackpack::new_with_expander`]; that's where a pin is checked against the chosen backend's
+/// actual [`Expander::PIN_COUNT`].
+const MAX_PIN: u8 = 15;
+
 #[inline]
 fn check_pin(pin: u8) {
-    if pin > 7 {
-        panic!("pins must be between 0 and 7");
+    if pin > MAX_PIN {
+        panic!("pins must be between 0 and {MAX_PIN}");
     }
 }
 
-impl<T: I2c<SevenBitAddress>> Hardware for I2cLcdBackpack<T> {
+impl<T: I2c<SevenBitAddress>, E: Expander<T>> Hardware for I2cLcdBackpack<T, E> {
     fn rs(&mut self, bit: bool) {
         if bit {
-            self.state |= 1 << self.pins.rs_pin;
+            self.state |= E::State::bit(self.pins.rs_pin);
         } else {
-            self.state &= !(1 << self.pins.rs_pin);
+            self.state &= !E::State::bit(self.pins.rs_pin);
         }
     }
 
     fn enable(&mut self, bit: bool) {
         if bit {
-            self.state |= 1 << self.pins.en_pin;
+            self.state |= E::State::bit(self.pins.en_pin);
         } else {
-            self.state &= !(1 << self.pins.en_pin);
+            self.state &= !E::State::bit(self.pins.en_pin);
         }
     }
 
     fn data(&mut self, data: u8) {
         if data & 0b0001 != 0 {
-            self.state |= 1 << self.pins.d4_pin;
+            self.state |= E::State::bit(self.pins.d4_pin);
         } else {
-            self.state &= !(1 << self.pins.d4_pin);
+            self.state &= !E::State::bit(self.pins.d4_pin);
         }
 
         if data & 0b0010 != 0 {
-            self.state |= 1 << self.pins.d5_pin;
+            self.state |= E::State::bit(self.pins.d5_pin);
         } else {
-            self.state &= !(1 << self.pins.d5_pin);
+            self.state &= !E::State::bit(self.pins.d5_pin);
         }
 
         if data & 0b0100 != 0 {
-            self.state |= 1 << self.pins.d6_pin;
+            self.state |= E::State::bit(self.pins.d6_pin);
         } else {
-            self.state &= !(1 << self.pins.d6_pin);
+            self.state &= !E::State::bit(self.pins.d6_pin);
         }
 
         if data & 0b1000 != 0 {
-            self.state |= 1 << self.pins.d7_pin;
+            self.state |= E::State::bit(self.pins.d7_pin);
         } else {
-            self.state &= !(1 << self.pins.d7_pin);
+            self.state &= !E::State::bit(self.pins.d7_pin);
         }
     }
 
@@ -159,31 +241,45 @@ impl<T: I2c<SevenBitAddress>> Hardware for I2cLcdBackpack<T> {
         if bit {
             // Configure all data pins as inputs.
             self.data(0b1111);
-            self.state |= 1 << self.pins.rw_pin;
+            self.state |= E::State::bit(self.pins.rw_pin);
         } else {
-            self.state &= !(1 << self.pins.rw_pin);
+            self.state &= !E::State::bit(self.pins.rw_pin);
         }
     }
 
     fn read_data(&mut self) -> u8 {
-        let mut result: [u8; 1] = [0; 1];
-        self.driver.read(self.address, &mut result).unwrap();
-        let result = result[0];
+        let mut readable = E::State::default();
+        readable |= E::State::bit(self.pins.d4_pin);
+        readable |= E::State::bit(self.pins.d5_pin);
+        readable |= E::State::bit(self.pins.d6_pin);
+        readable |= E::State::bit(self.pins.d7_pin);
+
+        let result = match self
+            .expander
+            .read_port(&mut self.driver, self.address, readable)
+        {
+            Ok(result) => result,
+            Err(err) => {
+                self.last_error = Some(err);
+                return 0;
+            }
+        };
 
+        let zero = E::State::default();
         let mut data = 0;
-        if result & (1 << self.pins.d4_pin) != 0 {
+        if result & E::State::bit(self.pins.d4_pin) != zero {
             data |= 0b0001;
         }
 
-        if result & (1 << self.pins.d5_pin) != 0 {
+        if result & E::State::bit(self.pins.d5_pin) != zero {
             data |= 0b0010;
         }
 
-        if result & (1 << self.pins.d6_pin) != 0 {
+        if result & E::State::bit(self.pins.d6_pin) != zero {
             data |= 0b0100;
         }
 
-        if result & (1 << self.pins.d7_pin) != 0 {
+        if result & E::State::bit(self.pins.d7_pin) != zero {
             data |= 0b1000;
         }
 
@@ -191,27 +287,39 @@ impl<T: I2c<SevenBitAddress>> Hardware for I2cLcdBackpack<T> {
     }
 
     fn apply(&mut self) {
-        self.driver.write(self.address, &[self.state]).unwrap();
+        if let Err(err) = self
+            .expander
+            .write_port(&mut self.driver, self.address, self.state)
+        {
+            self.last_error = Some(err);
+        }
     }
 }
 
-impl<T: Debug> Debug for I2cLcdBackpack<T> {
+impl<T, E> Debug for I2cLcdBackpack<T, E>
+where
+    T: I2c<SevenBitAddress> + Debug,
+    T::Error: Debug,
+    E: Expander<T> + Debug,
+{
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
         f.debug_struct("I2cLcdBackpack")
             .field("driver", &self.driver)
+            .field("expander", &self.expander)
             .field("address", &self.address)
             .field("state", &self.state)
             .field("pins", &self.pins)
+            .field("last_error", &self.last_error)
             .finish()
     }
 }
 
-impl<T: I2c<SevenBitAddress>> Backlight for I2cLcdBackpack<T> {
+impl<T: I2c<SevenBitAddress>, E: Expander<T>> Backlight for I2cLcdBackpack<T, E> {
     fn set_backlight(&mut self, enable: bool) {
         if enable {
-            self.state |= 1 << self.pins.backlight_pin;
+            self.state |= E::State::bit(self.pins.backlight_pin);
         } else {
-            self.state &= !(1 << self.pins.backlight_pin);
+            self.state &= !E::State::bit(self.pins.backlight_pin);
         }
 
         self.apply();
@@ -247,6 +355,25 @@ impl Default for I2cLcdPinConfig {
 }
 
 impl I2cLcdPinConfig {
+    /// The pin assignment used by the [Adafruit I2C/SPI LCD backpack](https://learn.adafruit.com/i2c-spi-lcd-backpack),
+    /// which is wired differently than the PCF8574 boards this crate otherwise targets. Pair this with the
+    /// [`Mcp23008`] expander backend and [`I2cLcdBackpack::new_with_expander`].
+    ///
+    /// The Adafruit board doesn't wire up a read line, so reading from the LCD is disabled, matching
+    /// [`rw(None)`][Self::rw].
+    pub fn adafruit_mcp23008() -> Self {
+        Self {
+            rw_pin: RW_PIN_NONE,
+            rs_pin: ADAFRUIT_MCP23008_RS_PIN,
+            en_pin: ADAFRUIT_MCP23008_EN_PIN,
+            d4_pin: ADAFRUIT_MCP23008_D4_PIN,
+            d5_pin: ADAFRUIT_MCP23008_D5_PIN,
+            d6_pin: ADAFRUIT_MCP23008_D6_PIN,
+            d7_pin: ADAFRUIT_MCP23008_D7_PIN,
+            backlight_pin: ADAFRUIT_MCP23008_BACKLIGHT_PIN,
+        }
+    }
+
     /// Set the read/write output from the PCF8574. If `None` is passed, reading from the LCD will be disabled.
     ///
     /// The default assignment is output 1.
@@ -323,4 +450,245 @@ impl I2cLcdPinConfig {
         self.backlight_pin = backlight_pin;
         self
     }
+
+    /// Panic if any configured pin is outside `0..pin_count`, the range actually addressable on the
+    /// backend this config is about to be paired with.
+    fn check_pin_count(&self, pin_count: u8) {
+        for pin in [
+            self.rs_pin,
+            self.en_pin,
+            self.d4_pin,
+            self.d5_pin,
+            self.d6_pin,
+            self.d7_pin,
+            self.backlight_pin,
+        ] {
+            assert!(
+                pin < pin_count,
+                "pin {pin} is out of range for a {pin_count}-pin expander"
+            );
+        }
+
+        if self.rw_pin != RW_PIN_NONE {
+            assert!(
+                self.rw_pin < pin_count,
+                "pin {} is out of range for a {pin_count}-pin expander",
+                self.rw_pin
+            );
+        }
+    }
+}
+
+/// The 7-bit address range used by the PCF8574 (address pins tied to `GND`..`VCC`).
+const PCF8574_ADDRESSES: RangeInclusive<u8> = 0x20..=0x27;
+
+/// The 7-bit address range used by the PCF8574A (address pins tied to `GND`..`VCC`).
+const PCF8574A_ADDRESSES: RangeInclusive<u8> = 0x38..=0x3f;
+
+/// Iterator over 7-bit I2C addresses that acknowledged a probe read, returned by [`scan`].
+pub struct ScanIter<'a, T> {
+    i2c: &'a mut T,
+    addresses: Chain<RangeInclusive<u8>, RangeInclusive<u8>>,
+}
+
+impl<T: I2c<SevenBitAddress>> Iterator for ScanIter<'_, T> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let mut probe = [0u8; 1];
+        self.addresses
+            .find(|&address| self.i2c.read(address, &mut probe).is_ok())
+    }
+}
+
+/// Probe the I2C bus for a PCF8574 (address `0x20`..=`0x27`) or PCF8574A (address `0x38`..=`0x3f`) by
+/// attempting a 1-byte read at each candidate address, returning an iterator over the addresses that
+/// acknowledged.
+///
+/// This mirrors the usual Arduino/Linux "I2C scanner" bring-up trick and saves guessing which of the two
+/// address ranges (and which address within it) a particular backpack's address pins are strapped to.
+/// [`Expander`] backends that live at other addresses (e.g. a PCA9554 at a non-default address) aren't
+/// covered by this scan; use [`embedded_hal`]'s `I2c::read` directly to probe those.
+pub fn scan<T: I2c<SevenBitAddress>>(i2c: &mut T) -> ScanIter<'_, T> {
+    ScanIter {
+        i2c,
+        addresses: PCF8574_ADDRESSES.chain(PCF8574A_ADDRESSES),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    use embedded_hal::i2c::{Error, ErrorKind, ErrorType, Operation};
+
+    const LCD_ADDR: u8 = 0x27;
+    const SENSOR_ADDR: u8 = 0x50;
+
+    #[derive(Debug)]
+    struct MockError;
+
+    impl Error for MockError {
+        fn kind(&self) -> ErrorKind {
+            ErrorKind::Other
+        }
+    }
+
+    /// A minimal I2C bus mock: each address holds a single byte of state that `write` sets and `read`
+    /// reports back, just like a PCF8574.
+    #[derive(Default)]
+    struct MockBus {
+        lcd: u8,
+        sensor: u8,
+    }
+
+    impl MockBus {
+        fn register_mut(&mut self, address: u8) -> &mut u8 {
+            match address {
+                LCD_ADDR => &mut self.lcd,
+                SENSOR_ADDR => &mut self.sensor,
+                _ => panic!("unexpected address {address}"),
+            }
+        }
+    }
+
+    impl ErrorType for MockBus {
+        type Error = MockError;
+    }
+
+    impl I2c<SevenBitAddress> for MockBus {
+        fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            buffer[0] = *self.register_mut(address);
+            Ok(())
+        }
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+            *self.register_mut(address) = bytes[0];
+            Ok(())
+        }
+
+        fn write_read(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            self.write(address, bytes)?;
+            self.read(address, buffer)
+        }
+
+        fn transaction(
+            &mut self,
+            _address: u8,
+            _operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            unimplemented!("no Expander backend uses I2c::transaction")
+        }
+    }
+
+    /// Stands in for `embedded-hal-bus`'s `RefCellDevice`: a shared-bus proxy that borrows the bus for
+    /// the duration of each transaction instead of owning it.
+    struct SharedBus<'a>(&'a RefCell<MockBus>);
+
+    impl ErrorType for SharedBus<'_> {
+        type Error = MockError;
+    }
+
+    impl I2c<SevenBitAddress> for SharedBus<'_> {
+        fn read(&mut self, address: u8, buffer: &mut [u8]) -> Result<(), Self::Error> {
+            self.0.borrow_mut().read(address, buffer)
+        }
+
+        fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Self::Error> {
+            self.0.borrow_mut().write(address, bytes)
+        }
+
+        fn write_read(
+            &mut self,
+            address: u8,
+            bytes: &[u8],
+            buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            self.0.borrow_mut().write_read(address, bytes, buffer)
+        }
+
+        fn transaction(
+            &mut self,
+            address: u8,
+            operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            self.0.borrow_mut().transaction(address, operations)
+        }
+    }
+
+    #[test]
+    fn backpack_shares_bus_with_a_second_device() {
+        let bus = RefCell::new(MockBus::default());
+
+        let mut lcd = I2cLcdBackpack::new(SharedBus(&bus), LCD_ADDR);
+        let mut sensor = SharedBus(&bus);
+
+        lcd.set_backlight(true);
+        sensor.write(SENSOR_ADDR, &[0x42]).unwrap();
+
+        assert_eq!(bus.borrow().lcd, 1 << DEFAULT_BACKLIGHT_PIN);
+        assert_eq!(bus.borrow().sensor, 0x42);
+    }
+
+    /// An I2C bus that ACKs a `read` only at a fixed set of addresses, used to test [`scan`].
+    struct ScanBus {
+        acks: &'static [u8],
+    }
+
+    impl ErrorType for ScanBus {
+        type Error = MockError;
+    }
+
+    impl I2c<SevenBitAddress> for ScanBus {
+        fn read(&mut self, address: u8, _buffer: &mut [u8]) -> Result<(), Self::Error> {
+            if self.acks.contains(&address) {
+                Ok(())
+            } else {
+                Err(MockError)
+            }
+        }
+
+        fn write(&mut self, _address: u8, _bytes: &[u8]) -> Result<(), Self::Error> {
+            unimplemented!("scan only reads")
+        }
+
+        fn write_read(
+            &mut self,
+            _address: u8,
+            _bytes: &[u8],
+            _buffer: &mut [u8],
+        ) -> Result<(), Self::Error> {
+            unimplemented!("scan only reads")
+        }
+
+        fn transaction(
+            &mut self,
+            _address: u8,
+            _operations: &mut [Operation<'_>],
+        ) -> Result<(), Self::Error> {
+            unimplemented!("scan only reads")
+        }
+    }
+
+    #[test]
+    fn scan_yields_only_addresses_that_acked_across_both_ranges() {
+        let mut bus = ScanBus {
+            acks: &[0x23, 0x3a],
+        };
+
+        let mut found = [0u8; 2];
+        let mut count = 0;
+        for address in scan(&mut bus) {
+            found[count] = address;
+            count += 1;
+        }
+
+        assert_eq!(count, 2);
+        assert_eq!(found, [0x23, 0x3a]);
+    }
 }